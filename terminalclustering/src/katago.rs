@@ -1,3 +1,4 @@
+use crate::sgf;
 use anyhow::{Result, anyhow};
 use rand::distr::{Distribution, weighted::WeightedIndex};
 use std::{
@@ -23,13 +24,23 @@ pub struct AnalysisRequest {
     pub board_y_size: i32,
 }
 
+/// The parts of a `Game`'s metadata that KataGo needs to analyze it
+/// correctly: its ruleset, komi, and board dimensions.
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    pub rules: String,
+    pub komi: f32,
+    pub board_x_size: i32,
+    pub board_y_size: i32,
+}
+
 #[derive(Debug, serde_derive::Serialize, serde_derive::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AnalysisResponseRootInfo {
     pub current_player: String,
 }
 
-#[derive(Debug, serde_derive::Serialize, serde_derive::Deserialize)]
+#[derive(Debug, Clone, serde_derive::Serialize, serde_derive::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AnalysisResponseMoveInfo {
     #[serde(rename = "move")]
@@ -46,6 +57,25 @@ pub struct AnalysisResponse {
     pub move_infos: Vec<AnalysisResponseMoveInfo>,
 }
 
+/// A played move together with the engine's evaluation of the position
+/// before it: the resulting score lead and all of the engine's candidate
+/// moves at that position (the played one included), for annotating the
+/// move in the output SGF.
+#[derive(Debug, Clone)]
+pub struct MoveRecord {
+    pub player: String,
+    pub mv: String,
+    pub score_lead: f32,
+    pub candidates: Vec<AnalysisResponseMoveInfo>,
+}
+
+/// The outcome of `KataGo::run_game`: every played move plus the result
+/// the game ended with, whether by a pass or a resignation.
+pub struct PlayedGame {
+    pub moves: Vec<MoveRecord>,
+    pub result: sgf::GameResult,
+}
+
 pub struct KataGo {
     /// Writable handle to the engine’s STDIN.
     stdin: Mutex<ChildStdin>,
@@ -120,17 +150,22 @@ impl KataGo {
     }
 
     /// Issue a single analysis request and wait for the final reply.
-    pub async fn analyze(&self, moves: Vec<(String, String)>) -> Result<AnalysisResponse> {
+    pub async fn analyze(
+        &self,
+        game_config: &GameConfig,
+        initial_stones: Vec<(String, String)>,
+        moves: Vec<(String, String)>,
+    ) -> Result<AnalysisResponse> {
         let id = self.next_id.fetch_add(1, Ordering::SeqCst).to_string();
 
         let req = AnalysisRequest {
             id: id.clone(),
-            initial_stones: vec![],
+            initial_stones,
             moves: moves.clone(),
-            rules: "tromp-taylor".into(),
-            komi: 7.5,
-            board_x_size: 19,
-            board_y_size: 19,
+            rules: game_config.rules.clone(),
+            komi: game_config.komi,
+            board_x_size: game_config.board_x_size,
+            board_y_size: game_config.board_y_size,
         };
         let (tx, rx) = oneshot::channel();
         self.pending.lock().await.insert(id.clone(), tx);
@@ -170,30 +205,75 @@ impl KataGo {
 
     pub async fn run_game(
         &self,
+        game_config: &GameConfig,
         initial_stones: Vec<(String, String)>,
-    ) -> Result<Vec<(String, String)>> {
-        let mut stones = initial_stones;
+        moves: Vec<(String, String)>,
+        resign_utility_threshold: f32,
+    ) -> Result<PlayedGame> {
+        // The opening moves loaded from the input SGF were never
+        // evaluated move-by-move, so record them unannotated rather than
+        // dropping them from the returned game.
+        let mut records: Vec<MoveRecord> = moves
+            .iter()
+            .map(|(player, mv)| MoveRecord {
+                player: player.clone(),
+                mv: mv.clone(),
+                score_lead: 0.0,
+                candidates: vec![],
+            })
+            .collect();
+        let mut stones = moves;
         loop {
-            let analysis_result = self.analyze(stones.clone()).await?;
+            let analysis_result = self
+                .analyze(game_config, initial_stones.clone(), stones.clone())
+                .await?;
             let mv = pick_move(&analysis_result.move_infos)?;
+            let current_player = analysis_result.root_info.current_player.clone();
+
+            // A pass is a normal game end even when the position is
+            // lopsided: a decisively lost position's best move is often
+            // "pass" with utility far below `resign_utility_threshold`,
+            // so this must be checked before the resignation branch.
             if mv.mov == "pass" {
-                return Ok(stones);
+                let black_score_lead =
+                    sgf::black_perspective_score_lead(&current_player, mv.score_lead);
+                let result = if black_score_lead >= 0. {
+                    sgf::GameResult::BlackWins(black_score_lead)
+                } else {
+                    sgf::GameResult::WhiteWins(-black_score_lead)
+                };
+                return Ok(PlayedGame {
+                    moves: records,
+                    result,
+                });
             }
-            let score_for_black = if analysis_result.root_info.current_player == "W" {
-                -mv.score_lead
-            } else {
-                mv.score_lead
-            };
+
+            if mv.utility <= resign_utility_threshold {
+                let result = if current_player == "B" {
+                    sgf::GameResult::WhiteWinsByResignation
+                } else {
+                    sgf::GameResult::BlackWinsByResignation
+                };
+                return Ok(PlayedGame {
+                    moves: records,
+                    result,
+                });
+            }
+
+            let score_for_black = sgf::black_perspective_score_lead(&current_player, mv.score_lead);
             let score_str = if score_for_black > 0. {
                 format!("B+{:.1}", score_for_black)
             } else {
                 format!("W+{:.1}", -score_for_black)
             };
-            println!("move {}: {} {}\t({})", stones.len(), analysis_result.root_info.current_player, mv.mov, score_str);
-            stones.push((
-                analysis_result.root_info.current_player.clone(),
-                mv.mov.clone(),
-            ));
+            println!("move {}: {} {}\t({})", stones.len(), current_player, mv.mov, score_str);
+            stones.push((current_player.clone(), mv.mov.clone()));
+            records.push(MoveRecord {
+                player: current_player,
+                mv: mv.mov.clone(),
+                score_lead: mv.score_lead,
+                candidates: analysis_result.move_infos,
+            });
         }
     }
 }
@@ -207,15 +287,20 @@ impl Drop for KataGo {
     }
 }
 
+/// Utility value at or below which a losing player resigns rather than playing
+/// on to a pass, absent an explicit `resignUtilityThreshold` config key.
+const DEFAULT_RESIGN_UTILITY_THRESHOLD: f32 = -0.98;
+
 #[derive(Debug)]
 pub struct Config {
     pub num_analysis_threads: usize,
+    pub resign_utility_threshold: f32,
 }
 
 pub fn parse_config(content: &str) -> Result<Config> {
     // Finds lines of the form:
     // myKey = myValue  # Optional comment
-    let key_value_regex = regex::Regex::new("^(\\w+)\\s*=\\s*(\\w+)\\s*(?:#.*)?$").unwrap();
+    let key_value_regex = regex::Regex::new("^(\\w+)\\s*=\\s*([\\w.+-]+)\\s*(?:#.*)?$").unwrap();
     let entries: HashMap<&str, &str> = content
         .lines()
         .filter_map(|line| {
@@ -231,5 +316,10 @@ pub fn parse_config(content: &str) -> Result<Config> {
             .get("numAnalysisThreads")
             .ok_or_else(|| anyhow::Error::msg("numAnalysisThreads is required"))?
             .parse()?,
+        resign_utility_threshold: entries
+            .get("resignUtilityThreshold")
+            .map(|v| v.parse())
+            .transpose()?
+            .unwrap_or(DEFAULT_RESIGN_UTILITY_THRESHOLD),
     })
 }
@@ -21,15 +21,68 @@ struct Args {
 
     #[arg(short, long)]
     playouts: i32,
+
+    #[arg(long)]
+    player_black: Option<String>,
+
+    #[arg(long)]
+    player_white: Option<String>,
+
+    #[arg(long)]
+    black_rank: Option<String>,
+
+    #[arg(long)]
+    white_rank: Option<String>,
+
+    #[arg(long)]
+    date: Option<String>,
+}
+
+fn game_record_with_result(
+    record: &sgf::GameRecord,
+    result: sgf::GameResult,
+    id: String,
+) -> sgf::GameRecord {
+    sgf::GameRecord {
+        result: Some(result),
+        id: Some(id),
+        ..record.clone()
+    }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    let game = sgf::load_sgf(&args.game)?;
+    let game_tree = sgf::load_sgf(&args.game)?;
 
-    let stones = sgf::sgf_to_stones(&game)?;
+    let game = sgf::interpret_game(&game_tree).map_err(anyhow::Error::new)?;
+    let initial_stones = sgf::initial_stones(&game);
+    let stones = sgf::moves(&game);
+    let game_config = katago::GameConfig {
+        rules: game.rules.clone(),
+        komi: game.komi,
+        board_x_size: game.board_size.0 as i32,
+        board_y_size: game.board_size.1 as i32,
+    };
+    let game_record = sgf::GameRecord {
+        board_size: game.board_size,
+        komi: game.komi,
+        rules: game.rules.clone(),
+        players: (
+            sgf::Player {
+                name: args.player_black.clone(),
+                rank: args.black_rank.clone().map(sgf::Rank),
+            },
+            sgf::Player {
+                name: args.player_white.clone(),
+                rank: args.white_rank.clone().map(sgf::Rank),
+            },
+        ),
+        date: args.date.clone().map(sgf::Date),
+        result: None,
+        id: None,
+    };
 
     let config = katago::parse_config(std::fs::read_to_string(&args.config)?.as_str())?;
     println!("Using config: {config:?}");
@@ -52,9 +105,8 @@ async fn main() -> anyhow::Result<()> {
         i += 1;
         games.push(game);
 
-        let joined = sgf::combine_sgfs(games.as_slice())?;
-        sgf::save_game_sgf(
-            &joined,
+        sgf::save_game_collection_sgf(
+            games.as_slice(),
             format!(
                 "/home/jtstogel/github/jtstogel/kataplay/terminalclustering/sgfs/outputs/joined_{joined_i}.sgf"
             ).as_str()
@@ -66,18 +118,46 @@ async fn main() -> anyhow::Result<()> {
         }
         Ok(())
     };
+    // A single malformed playout (e.g. an input SGF whose continuation
+    // ends before a single move is analyzed) shouldn't abort every other
+    // in-flight and queued game in the batch, so failures here are
+    // logged and skipped rather than propagated.
+    let mut save_played = |played: katago::PlayedGame| {
+        let id = uuid::Uuid::new_v4().to_string();
+        let record = game_record_with_result(&game_record, played.result, id);
+        let result =
+            sgf::stones_to_annotated_sgf(&played.moves, &initial_stones, game.board_size, &record)
+                .and_then(|tree| save(tree));
+        if let Err(e) = result {
+            eprintln!("failed to save game: {e}");
+        }
+    };
+    // `run_game` itself can fail just as easily (e.g. the analysis channel
+    // closing mid-game), so it gets the same log-and-skip treatment as a
+    // failed save rather than aborting every other in-flight and queued
+    // game via `?`.
+    let mut handle_played_result = |result: anyhow::Result<katago::PlayedGame>| match result {
+        Result::Ok(played) => save_played(played),
+        Err(e) => eprintln!("failed to run game: {e}"),
+    };
 
     for _ in 0..args.playouts {
         let kg = kg.clone();
+        let game_config = game_config.clone();
+        let initial_stones = initial_stones.clone();
         let stones = stones.clone();
-        futures.push(async move { kg.run_game(stones).await });
+        let resign_utility_threshold = config.resign_utility_threshold;
+        futures.push(async move {
+            kg.run_game(&game_config, initial_stones, stones, resign_utility_threshold)
+                .await
+        });
 
         if futures.len() == config.num_analysis_threads {
-            save(sgf::stones_to_sgf(&futures.next().await.unwrap()?)?)?;
+            handle_played_result(futures.next().await.unwrap());
         }
     }
     while !futures.is_empty() {
-        save(sgf::stones_to_sgf(&futures.next().await.unwrap()?)?)?;
+        handle_played_result(futures.next().await.unwrap());
     }
     Ok(())
 }
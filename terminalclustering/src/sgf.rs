@@ -2,6 +2,8 @@ use std::{fs, vec};
 
 use sgf_parse::SgfProp;
 
+use crate::katago;
+
 pub fn load_sgf(path: &str) -> anyhow::Result<sgf_parse::GameTree> {
     let content = fs::read_to_string(path)?;
     let mut trees = sgf_parse::parse(&content)?;
@@ -10,17 +12,24 @@ pub fn load_sgf(path: &str) -> anyhow::Result<sgf_parse::GameTree> {
         .ok_or_else(|| anyhow::Error::msg("no game tree found"))
 }
 
-fn move_to_string(mv: &sgf_parse::go::Move) -> String {
+/// Board size in SGF coordinates, as (width, height) in line count.
+pub const DEFAULT_BOARD_SIZE: (u8, u8) = (19, 19);
+
+fn point_to_string(point: &sgf_parse::go::Point, board_size: (u8, u8)) -> String {
     let rank: &'static [u8] = "ABCDEFGHJKLMNOPQRST".as_bytes();
+    debug_assert!(usize::from(point.x) < usize::from(board_size.0));
+    debug_assert!(usize::from(point.y) < usize::from(board_size.1));
+    (rank[usize::from(point.x)] as char).to_string() + (point.y + 1).to_string().as_str()
+}
+
+fn move_to_string(mv: &sgf_parse::go::Move, board_size: (u8, u8)) -> String {
     match mv {
         sgf_parse::go::Move::Pass => "pass".into(),
-        sgf_parse::go::Move::Move(point) => {
-            (rank[usize::from(point.x)] as char).to_string() + (point.y + 1).to_string().as_str()
-        }
+        sgf_parse::go::Move::Move(point) => point_to_string(point, board_size),
     }
 }
 
-fn string_to_move(mv: &String) -> sgf_parse::go::Move {
+fn string_to_move(mv: &str, board_size: (u8, u8)) -> sgf_parse::go::Move {
     if mv == "pass" {
         return sgf_parse::go::Move::Pass;
     }
@@ -28,6 +37,8 @@ fn string_to_move(mv: &String) -> sgf_parse::go::Move {
     let x_char = mv.as_bytes()[0];
     let x = rank.iter().position(|v| *v == x_char).unwrap() as u8;
     let y = mv[1..].parse::<u8>().unwrap() - 1;
+    debug_assert!(usize::from(x) < usize::from(board_size.0));
+    debug_assert!(usize::from(y) < usize::from(board_size.1));
     sgf_parse::go::Move::Move(sgf_parse::go::Point { x, y })
 }
 
@@ -40,24 +51,24 @@ pub fn sgf_to_stones(sgf: &sgf_parse::GameTree) -> anyhow::Result<Vec<(String, S
                 return None;
             };
             match prop {
-                sgf_parse::go::Prop::B(mv) => Some(("B".into(), move_to_string(mv))),
-                sgf_parse::go::Prop::W(mv) => Some(("W".into(), move_to_string(mv))),
+                sgf_parse::go::Prop::B(mv) => {
+                    Some(("B".into(), move_to_string(mv, DEFAULT_BOARD_SIZE)))
+                }
+                sgf_parse::go::Prop::W(mv) => {
+                    Some(("W".into(), move_to_string(mv, DEFAULT_BOARD_SIZE)))
+                }
                 _ => None,
             }
         })
         .collect())
 }
 
-pub fn stones_to_sgf(stones: &Vec<(String, String)>) -> anyhow::Result<sgf_parse::GameTree> {
+pub fn stones_to_sgf(
+    stones: &Vec<(String, String)>,
+    board_size: (u8, u8),
+) -> anyhow::Result<sgf_parse::GameTree> {
     let nodes = stones.iter().rev().fold(None, |acc, (player, mv)| {
-        let mv_parsed = string_to_move(mv);
-        let sgf_move = match mv_parsed {
-            sgf_parse::go::Move::Pass => "".into(),
-            sgf_parse::go::Move::Move(point) => {
-                ((point.x + ('a' as u8)) as char).to_string()
-                    + ((point.y + ('a' as u8)) as char).to_string().as_str()
-            }
-        };
+        let sgf_move = move_to_sgf_coordinate(&string_to_move(mv, board_size));
         let properties = vec![sgf_parse::go::Prop::new(player.clone(), vec![sgf_move])];
 
         let Some(child) = acc else {
@@ -70,20 +81,543 @@ pub fn stones_to_sgf(stones: &Vec<(String, String)>) -> anyhow::Result<sgf_parse
     Ok(sgf_parse::GameTree::GoGame(root))
 }
 
+/// Render a move in the raw two-letter coordinate form SGF move
+/// properties (B/W) are written with, e.g. `"pd"`.
+fn move_to_sgf_coordinate(mv: &sgf_parse::go::Move) -> String {
+    match mv {
+        sgf_parse::go::Move::Pass => "".into(),
+        sgf_parse::go::Move::Move(point) => {
+            ((point.x + b'a') as char).to_string() + ((point.y + b'a') as char).to_string().as_str()
+        }
+    }
+}
+
 type GoSgfNode = sgf_parse::SgfNode<sgf_parse::go::Prop>;
 
-/// Combine all SGFs into one big game tree.
-pub fn combine_sgfs(games: &[sgf_parse::GameTree]) -> anyhow::Result<sgf_parse::GameTree> {
-    let nodes = games
-        .iter()
-        .map(|g| g.as_go_node())
+/// A player's rank, as written in an SGF BR/WR property (e.g. `"5d"`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rank(pub String);
+
+/// A date, as written in an SGF DT property (e.g. `"2024-03-01"`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Date(pub String);
+
+/// A finished game's outcome, as written in an SGF RE property.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameResult {
+    BlackWins(f32),
+    WhiteWins(f32),
+    BlackWinsByResignation,
+    WhiteWinsByResignation,
+}
+
+impl GameResult {
+    /// Render in SGF RE property form, e.g. `"B+3.5"` or `"W+R"`.
+    pub fn to_sgf_string(self) -> String {
+        match self {
+            GameResult::BlackWins(margin) => format!("B+{margin:.1}"),
+            GameResult::WhiteWins(margin) => format!("W+{margin:.1}"),
+            GameResult::BlackWinsByResignation => "B+R".into(),
+            GameResult::WhiteWinsByResignation => "W+R".into(),
+        }
+    }
+
+    /// Parse an SGF RE property value, e.g. `"B+3.5"` or `"W+R"`.
+    pub fn parse(s: &str) -> Option<GameResult> {
+        let (winner, margin) = s.split_once('+')?;
+        if margin.eq_ignore_ascii_case("r") || margin.eq_ignore_ascii_case("resign") {
+            return match winner {
+                "B" => Some(GameResult::BlackWinsByResignation),
+                "W" => Some(GameResult::WhiteWinsByResignation),
+                _ => None,
+            };
+        }
+        let margin: f32 = margin.parse().ok()?;
+        match winner {
+            "B" => Some(GameResult::BlackWins(margin)),
+            "W" => Some(GameResult::WhiteWins(margin)),
+            _ => None,
+        }
+    }
+}
+
+/// The black-perspective score lead implied by a score lead reported
+/// from `player`'s point of view, e.g. for normalizing KataGo's
+/// mover-relative `scoreLead` into a single consistent sign convention.
+pub fn black_perspective_score_lead(player: &str, score_lead: f32) -> f32 {
+    if player == "W" { -score_lead } else { score_lead }
+}
+
+/// One of the two players in a `Game`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Player {
+    pub name: Option<String>,
+    pub rank: Option<Rank>,
+}
+
+/// A single node along a game's main line, once it's been checked to
+/// unambiguously represent either a move or a position setup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameNode {
+    Move(String, String),
+    Setup {
+        add_black: Vec<String>,
+        add_white: Vec<String>,
+        add_empty: Vec<String>,
+    },
+}
+
+/// A validated, semantically meaningful game, as opposed to the raw
+/// syntactic `sgf_parse::GameTree` it was interpreted from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Game {
+    pub players: (Player, Player),
+    pub board_size: (u8, u8),
+    pub komi: f32,
+    pub rules: String,
+    pub result: Option<GameResult>,
+    pub date: Option<Date>,
+    pub handicap: i32,
+    pub main_line: Vec<GameNode>,
+}
+
+/// Why a single SGF node couldn't be interpreted as a `GameNode`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameNodeError {
+    /// The node carries both setup properties (AB/AW/AE) and move
+    /// properties (B/W), which together don't describe a single
+    /// unambiguous game action.
+    ConflictingProperty,
+}
+
+impl std::fmt::Display for GameNodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameNodeError::ConflictingProperty => write!(
+                f,
+                "node mixes setup properties (AB/AW/AE) with move properties (B/W)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GameNodeError {}
+
+/// Why a `sgf_parse::GameTree` couldn't be interpreted as a `Game`.
+#[derive(Debug)]
+pub enum GameError {
+    Node(GameNodeError),
+    Tree(anyhow::Error),
+    /// The root's HA (handicap) count didn't match the number of black
+    /// stones placed by the leading AB setup nodes.
+    HandicapMismatch { handicap: i32, placed: usize },
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::Node(e) => write!(f, "invalid game node: {e}"),
+            GameError::Tree(e) => write!(f, "invalid game tree: {e}"),
+            GameError::HandicapMismatch { handicap, placed } => write!(
+                f,
+                "HA declares {handicap} handicap stones but the leading setup placed {placed}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<GameNodeError> for GameError {
+    fn from(e: GameNodeError) -> Self {
+        GameError::Node(e)
+    }
+}
+
+impl From<anyhow::Error> for GameError {
+    fn from(e: anyhow::Error) -> Self {
+        GameError::Tree(e)
+    }
+}
+
+struct RootMetadata {
+    player_black: Option<String>,
+    player_white: Option<String>,
+    rank_black: Option<Rank>,
+    rank_white: Option<Rank>,
+    result: Option<GameResult>,
+    date: Option<Date>,
+    handicap: i32,
+    board_size: (u8, u8),
+    komi: f32,
+    rules: Option<String>,
+}
+
+impl Default for RootMetadata {
+    fn default() -> Self {
+        RootMetadata {
+            player_black: None,
+            player_white: None,
+            rank_black: None,
+            rank_white: None,
+            result: None,
+            date: None,
+            handicap: 0,
+            board_size: DEFAULT_BOARD_SIZE,
+            komi: 7.5,
+            rules: None,
+        }
+    }
+}
+
+fn parse_root_metadata(root: &GoSgfNode) -> RootMetadata {
+    let mut meta = RootMetadata::default();
+    for prop in root.properties() {
+        match prop {
+            sgf_parse::go::Prop::PB(v) => meta.player_black = Some(v.to_string()),
+            sgf_parse::go::Prop::PW(v) => meta.player_white = Some(v.to_string()),
+            sgf_parse::go::Prop::BR(v) => meta.rank_black = Some(Rank(v.to_string())),
+            sgf_parse::go::Prop::WR(v) => meta.rank_white = Some(Rank(v.to_string())),
+            sgf_parse::go::Prop::RE(v) => meta.result = GameResult::parse(&v.to_string()),
+            sgf_parse::go::Prop::DT(v) => meta.date = Some(Date(v.to_string())),
+            sgf_parse::go::Prop::HA(v) => meta.handicap = *v,
+            sgf_parse::go::Prop::SZ(width, height) => meta.board_size = (*width, *height),
+            sgf_parse::go::Prop::KM(komi) => meta.komi = *komi as f32,
+            sgf_parse::go::Prop::RU(rules) => meta.rules = Some(rules.to_string()),
+            _ => {}
+        }
+    }
+    meta
+}
+
+/// Points added to the board by a setup node's AB/AW/AE properties.
+fn setup_points(node: &GoSgfNode, board_size: (u8, u8)) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let mut add_black = vec![];
+    let mut add_white = vec![];
+    let mut add_empty = vec![];
+    for prop in node.properties() {
+        match prop {
+            sgf_parse::go::Prop::AB(points) => {
+                add_black.extend(points.iter().map(|p| point_to_string(p, board_size)))
+            }
+            sgf_parse::go::Prop::AW(points) => {
+                add_white.extend(points.iter().map(|p| point_to_string(p, board_size)))
+            }
+            sgf_parse::go::Prop::AE(points) => {
+                add_empty.extend(points.iter().map(|p| point_to_string(p, board_size)))
+            }
+            _ => {}
+        }
+    }
+    (add_black, add_white, add_empty)
+}
+
+fn interpret_node(node: &GoSgfNode, board_size: (u8, u8)) -> Result<GameNode, GameNodeError> {
+    let (add_black, add_white, add_empty) = setup_points(node, board_size);
+    let is_setup = !add_black.is_empty() || !add_white.is_empty() || !add_empty.is_empty();
+    let mv = node.get_move();
+
+    if is_setup && mv.is_some() {
+        return Err(GameNodeError::ConflictingProperty);
+    }
+
+    if let Some(prop) = mv {
+        return Ok(match prop {
+            sgf_parse::go::Prop::B(mv) => {
+                GameNode::Move("B".into(), move_to_string(mv, board_size))
+            }
+            sgf_parse::go::Prop::W(mv) => {
+                GameNode::Move("W".into(), move_to_string(mv, board_size))
+            }
+            _ => unreachable!("get_move only ever returns a B or W property"),
+        });
+    }
+
+    Ok(GameNode::Setup {
+        add_black,
+        add_white,
+        add_empty,
+    })
+}
+
+/// Interpret the raw syntactic node tree produced by `sgf_parse` into a
+/// validated `Game`, rejecting nodes that don't unambiguously represent
+/// either a move or a position setup.
+pub fn interpret_game(tree: &sgf_parse::GameTree) -> Result<Game, GameError> {
+    let root = tree.as_go_node().map_err(anyhow::Error::from)?;
+    let meta = parse_root_metadata(root);
+
+    let main_line = root
+        .main_variation()
+        .map(|node| interpret_node(node, meta.board_size))
         .collect::<Result<Vec<_>, _>>()?;
-    let root = GoSgfNode::new(
-        vec![sgf_parse::go::Prop::new("B".into(), vec!["".into()])],
-        nodes.iter().map(|x| (*x).clone()).collect::<Vec<_>>(),
-        true,
-    );
 
+    // HA declares a handicap stone count; the AB stones placed by the
+    // leading setup nodes are assumed to be exactly that handicap, so a
+    // mismatch means the SGF's setup doesn't match its own HA property.
+    if meta.handicap > 0 {
+        let (handicap_black, _) = leading_setup_stones(&main_line);
+        if handicap_black.len() != meta.handicap as usize {
+            return Err(GameError::HandicapMismatch {
+                handicap: meta.handicap,
+                placed: handicap_black.len(),
+            });
+        }
+    }
+
+    Ok(Game {
+        players: (
+            Player {
+                name: meta.player_black,
+                rank: meta.rank_black,
+            },
+            Player {
+                name: meta.player_white,
+                rank: meta.rank_white,
+            },
+        ),
+        board_size: meta.board_size,
+        komi: meta.komi,
+        rules: meta.rules.unwrap_or_else(|| "tromp-taylor".into()),
+        result: meta.result,
+        date: meta.date,
+        handicap: meta.handicap,
+        main_line,
+    })
+}
+
+/// The black and white stones placed by the leading setup nodes at the
+/// start of a main line (handicap/position setup via AB/AW/AE). Later AE
+/// removals are reconciled against earlier adds so the returned sets
+/// reflect the final board, not the raw property order.
+fn leading_setup_stones(
+    main_line: &[GameNode],
+) -> (
+    std::collections::BTreeSet<String>,
+    std::collections::BTreeSet<String>,
+) {
+    let mut black = std::collections::BTreeSet::new();
+    let mut white = std::collections::BTreeSet::new();
+
+    for node in main_line {
+        let GameNode::Setup {
+            add_black,
+            add_white,
+            add_empty,
+        } = node
+        else {
+            break;
+        };
+        for point in add_black {
+            white.remove(point);
+            black.insert(point.clone());
+        }
+        for point in add_white {
+            black.remove(point);
+            white.insert(point.clone());
+        }
+        for point in add_empty {
+            black.remove(point);
+            white.remove(point);
+        }
+    }
+
+    (black, white)
+}
+
+/// Stones already on the board before the first move is played, derived
+/// from the handicap/setup nodes (AB/AW/AE) at the start of the main line.
+pub fn initial_stones(game: &Game) -> Vec<(String, String)> {
+    let (black, white) = leading_setup_stones(&game.main_line);
+    black
+        .into_iter()
+        .map(|point| ("B".into(), point))
+        .chain(white.into_iter().map(|point| ("W".into(), point)))
+        .collect()
+}
+
+/// The played moves (B/W) in a game's main line, skipping the leading
+/// setup nodes that place handicap/initial stones.
+pub fn moves(game: &Game) -> Vec<(String, String)> {
+    game.main_line
+        .iter()
+        .skip_while(|node| matches!(node, GameNode::Setup { .. }))
+        .filter_map(|node| match node {
+            GameNode::Move(player, mv) => Some((player.clone(), mv.clone())),
+            GameNode::Setup { .. } => None,
+        })
+        .collect()
+}
+
+/// How many alternative candidates to list in a move's comment.
+const ANNOTATED_CANDIDATE_COUNT: usize = 3;
+
+/// Score lead given up versus the engine's best candidate, past which a
+/// played move is marked doubtful (DO) rather than left unannotated.
+const DOUBTFUL_SCORE_DROP: f32 = 0.75;
+
+/// Score lead given up versus the engine's best candidate, past which a
+/// played move is marked a mistake (BM) rather than merely doubtful.
+const BAD_MOVE_SCORE_DROP: f32 = 2.0;
+
+fn score_estimate_comment(eval: &katago::MoveRecord) -> String {
+    let black_score_lead = black_perspective_score_lead(&eval.player, eval.score_lead);
+    let estimate = if black_score_lead >= 0.0 {
+        format!("B+{black_score_lead:.1}")
+    } else {
+        format!("W+{:.1}", -black_score_lead)
+    };
+
+    let mut candidates = eval.candidates.iter().collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.score_lead.total_cmp(&a.score_lead));
+
+    std::iter::once(estimate)
+        .chain(
+            candidates
+                .into_iter()
+                .take(ANNOTATED_CANDIDATE_COUNT)
+                .map(|c| format!("{}: {:+.1}", c.mov, c.score_lead)),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A move-annotation property (TE/BM/DO-style) for a move that gave up
+/// significant score versus the engine's best candidate at that position.
+fn move_annotation(eval: &katago::MoveRecord) -> Option<sgf_parse::go::Prop> {
+    let best_score_lead = eval
+        .candidates
+        .iter()
+        .map(|c| c.score_lead)
+        .fold(f32::MIN, f32::max);
+    let score_drop = best_score_lead - eval.score_lead;
+
+    if score_drop >= BAD_MOVE_SCORE_DROP {
+        Some(sgf_parse::go::Prop::BM(sgf_parse::go::Double::Double))
+    } else if score_drop >= DOUBTFUL_SCORE_DROP {
+        Some(sgf_parse::go::Prop::DO)
+    } else {
+        None
+    }
+}
+
+/// Root-level metadata to attach to a saved game record: the board size,
+/// komi, and ruleset the game was played under, the players, the date it
+/// was played, its final result, and a stable identifier that survives
+/// batching the game into a collection file.
+#[derive(Debug, Clone, Default)]
+pub struct GameRecord {
+    pub board_size: (u8, u8),
+    pub komi: f32,
+    pub rules: String,
+    pub players: (Player, Player),
+    pub date: Option<Date>,
+    pub result: Option<GameResult>,
+    /// A UUID tagging this specific playout, written as a GC property so
+    /// the game can be found again inside a batched `joined_*.sgf`
+    /// collection.
+    pub id: Option<String>,
+}
+
+fn game_record_properties(record: &GameRecord) -> Vec<sgf_parse::go::Prop> {
+    let mut properties = vec![
+        sgf_parse::go::Prop::SZ(record.board_size.0, record.board_size.1),
+        sgf_parse::go::Prop::KM(record.komi as f64),
+        sgf_parse::go::Prop::RU(record.rules.clone().into()),
+    ];
+    if let Some(name) = &record.players.0.name {
+        properties.push(sgf_parse::go::Prop::PB(name.clone().into()));
+    }
+    if let Some(name) = &record.players.1.name {
+        properties.push(sgf_parse::go::Prop::PW(name.clone().into()));
+    }
+    if let Some(rank) = &record.players.0.rank {
+        properties.push(sgf_parse::go::Prop::BR(rank.0.clone().into()));
+    }
+    if let Some(rank) = &record.players.1.rank {
+        properties.push(sgf_parse::go::Prop::WR(rank.0.clone().into()));
+    }
+    if let Some(date) = &record.date {
+        properties.push(sgf_parse::go::Prop::DT(date.0.clone().into()));
+    }
+    if let Some(result) = &record.result {
+        properties.push(sgf_parse::go::Prop::RE(result.to_sgf_string().into()));
+    }
+    if let Some(id) = &record.id {
+        properties.push(sgf_parse::go::Prop::new("GC".into(), vec![id.clone()]));
+    }
+    properties
+}
+
+/// The AB/AW setup properties for the stones already on the board before
+/// a game's first move (see `initial_stones`), to attach to the first
+/// node of a saved game record so handicap/setup games aren't written as
+/// if they started from an empty board.
+fn setup_properties(
+    initial_stones: &[(String, String)],
+    board_size: (u8, u8),
+) -> Vec<sgf_parse::go::Prop> {
+    let mut add_black = vec![];
+    let mut add_white = vec![];
+    for (player, mv) in initial_stones {
+        let sgf_parse::go::Move::Move(point) = string_to_move(mv, board_size) else {
+            continue;
+        };
+        match player.as_str() {
+            "B" => add_black.push(point),
+            "W" => add_white.push(point),
+            _ => {}
+        }
+    }
+
+    let mut properties = vec![];
+    if !add_black.is_empty() {
+        properties.push(sgf_parse::go::Prop::AB(add_black));
+    }
+    if !add_white.is_empty() {
+        properties.push(sgf_parse::go::Prop::AW(add_white));
+    }
+    properties
+}
+
+/// Build an SGF tree whose nodes are annotated with the engine's
+/// evaluation of each move: a running score estimate and top candidate
+/// moves in a C[] comment, plus a move-annotation property on moves that
+/// gave up significant score versus the engine's best candidate. The
+/// leading handicap/setup stones and the players, date, and result in
+/// `record` are written to a root node of their own, ahead of the first
+/// move node.
+pub fn stones_to_annotated_sgf(
+    moves: &[katago::MoveRecord],
+    initial_stones: &[(String, String)],
+    board_size: (u8, u8),
+    record: &GameRecord,
+) -> anyhow::Result<sgf_parse::GameTree> {
+    let nodes = moves.iter().rev().fold(None, |acc, eval| {
+        let sgf_move = move_to_sgf_coordinate(&string_to_move(&eval.mv, board_size));
+
+        let mut properties = vec![sgf_parse::go::Prop::new(eval.player.clone(), vec![sgf_move])];
+        // Moves loaded from the input SGF (see `katago::run_game`) carry
+        // no candidates because they were never analyzed; formatting a
+        // comment for them would fabricate an engine verdict that was
+        // never actually computed.
+        if !eval.candidates.is_empty() {
+            properties.push(sgf_parse::go::Prop::C(score_estimate_comment(eval).into()));
+            properties.extend(move_annotation(eval));
+        }
+
+        let Some(child) = acc else {
+            return Some(sgf_parse::SgfNode::new(properties, vec![], false));
+        };
+        Some(sgf_parse::SgfNode::new(properties, vec![child], false))
+    });
+
+    let first_move = nodes.ok_or_else(|| anyhow::Error::msg("bad board"))?;
+    // Setup stones get their own node: `interpret_game` rejects a node
+    // that mixes AB/AW with a B/W move.
+    let mut root_properties = setup_properties(initial_stones, board_size);
+    root_properties.extend(game_record_properties(record));
+    let root = sgf_parse::SgfNode::new(root_properties, vec![first_move], true);
     Ok(sgf_parse::GameTree::GoGame(root))
 }
 
@@ -91,17 +625,265 @@ pub fn save_game_sgf(game: &sgf_parse::GameTree, path: &str) -> anyhow::Result<(
     Ok(fs::write(path, game.as_go_node()?.serialize())?)
 }
 
+/// Write a real SGF collection: each game is serialized as its own
+/// top-level `(;...)` tree, one after another, rather than nested under
+/// a synthetic parent move node. Each game is expected to already carry
+/// its own identity (see `GameRecord::id`) so it can be found again
+/// inside the batched collection file.
+pub fn save_game_collection_sgf(games: &[sgf_parse::GameTree], path: &str) -> anyhow::Result<()> {
+    let serialized = games
+        .iter()
+        .map(|g| Ok(g.as_go_node()?.serialize()))
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .join("\n");
+    Ok(fs::write(path, serialized)?)
+}
+
 #[cfg(test)]
 mod test {
-    use crate::sgf::{move_to_string, string_to_move};
+    use crate::katago;
+    use crate::sgf::{
+        initial_stones, interpret_game, move_annotation, move_to_string,
+        save_game_collection_sgf, stones_to_annotated_sgf, string_to_move, GameError,
+        GameNodeError, GameRecord, GameResult, Player, DEFAULT_BOARD_SIZE,
+    };
+
+    fn parse_one(sgf: &str) -> sgf_parse::GameTree {
+        sgf_parse::parse(sgf).unwrap().pop().unwrap()
+    }
 
     #[test]
     fn test_move_string_conversions() {
         for x in 0..19u8 {
             for y in 0..19u8 {
                 let mv = sgf_parse::go::Move::Move(sgf_parse::go::Point { x, y });
-                assert_eq!(mv, string_to_move(&move_to_string(&mv)));
+                assert_eq!(
+                    mv,
+                    string_to_move(&move_to_string(&mv, DEFAULT_BOARD_SIZE), DEFAULT_BOARD_SIZE)
+                );
             }
         }
     }
+
+    #[test]
+    fn test_move_string_conversions_rectangular_board() {
+        let board_size = (9, 13);
+        for x in 0..board_size.0 {
+            for y in 0..board_size.1 {
+                let mv = sgf_parse::go::Move::Move(sgf_parse::go::Point { x, y });
+                assert_eq!(
+                    mv,
+                    string_to_move(&move_to_string(&mv, board_size), board_size)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_node_mixing_setup_and_move_properties_is_rejected() {
+        let tree = parse_one("(;GM[1]FF[4]SZ[19];AB[pd]B[qf])");
+        let err = interpret_game(&tree).unwrap_err();
+        assert!(matches!(
+            err,
+            GameError::Node(GameNodeError::ConflictingProperty)
+        ));
+    }
+
+    #[test]
+    fn test_initial_stones_reconciles_ae_across_setup_nodes() {
+        // AB[pd][dp] places black at Q4 and D16; a later setup node adds
+        // white at D4 and removes the earlier black stone at Q4 via AE,
+        // so only D16 (black) and D4 (white) should remain.
+        let tree = parse_one("(;GM[1]FF[4]SZ[19]AB[pd][dp];AW[dd]AE[pd];B[qf])");
+        let game = interpret_game(&tree).unwrap();
+        let mut stones = initial_stones(&game);
+        stones.sort();
+        assert_eq!(
+            stones,
+            vec![("B".to_string(), "D16".to_string()), ("W".to_string(), "D4".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_handicap_matching_ab_count_is_accepted() {
+        let tree = parse_one("(;GM[1]FF[4]SZ[19]HA[2]AB[pd][dp];B[qf])");
+        let game = interpret_game(&tree).unwrap();
+        assert_eq!(game.handicap, 2);
+    }
+
+    #[test]
+    fn test_handicap_mismatching_ab_count_is_rejected() {
+        let tree = parse_one("(;GM[1]FF[4]SZ[19]HA[3]AB[pd][dp];B[qf])");
+        let err = interpret_game(&tree).unwrap_err();
+        assert!(matches!(
+            err,
+            GameError::HandicapMismatch {
+                handicap: 3,
+                placed: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn test_game_result_round_trips_through_sgf_string() {
+        for result in [
+            GameResult::BlackWins(3.5),
+            GameResult::WhiteWins(0.5),
+            GameResult::BlackWinsByResignation,
+            GameResult::WhiteWinsByResignation,
+        ] {
+            assert_eq!(GameResult::parse(&result.to_sgf_string()), Some(result));
+        }
+    }
+
+    #[test]
+    fn test_stones_to_annotated_sgf_skips_comment_for_unanalyzed_moves() {
+        // Moves loaded from the input SGF carry no candidates (see
+        // `katago::run_game`); they must not get a fabricated C[] comment.
+        let moves = vec![katago::MoveRecord {
+            player: "B".into(),
+            mv: "Q4".into(),
+            score_lead: 0.0,
+            candidates: vec![],
+        }];
+
+        let tree = stones_to_annotated_sgf(&moves, &[], DEFAULT_BOARD_SIZE, &GameRecord::default())
+            .unwrap();
+        let sgf_text = tree.as_go_node().unwrap().serialize();
+
+        assert!(!sgf_text.contains("C["));
+    }
+
+    fn candidate(mov: &str, score_lead: f32) -> katago::AnalysisResponseMoveInfo {
+        katago::AnalysisResponseMoveInfo {
+            mov: mov.into(),
+            utility: 0.0,
+            score_lead,
+        }
+    }
+
+    fn move_record(
+        score_lead: f32,
+        candidates: Vec<katago::AnalysisResponseMoveInfo>,
+    ) -> katago::MoveRecord {
+        katago::MoveRecord {
+            player: "B".into(),
+            mv: "qf".into(),
+            score_lead,
+            candidates,
+        }
+    }
+
+    #[test]
+    fn test_move_annotation_thresholds() {
+        let candidates = vec![candidate("pd", 5.0)];
+
+        // Giving up less than DOUBTFUL_SCORE_DROP isn't annotated.
+        assert!(move_annotation(&move_record(4.5, candidates.clone())).is_none());
+
+        // Giving up at least DOUBTFUL_SCORE_DROP but less than
+        // BAD_MOVE_SCORE_DROP is marked doubtful.
+        assert!(matches!(
+            move_annotation(&move_record(4.2, candidates.clone())),
+            Some(sgf_parse::go::Prop::DO)
+        ));
+
+        // Giving up at least BAD_MOVE_SCORE_DROP is marked a mistake.
+        assert!(matches!(
+            move_annotation(&move_record(2.9, candidates)),
+            Some(sgf_parse::go::Prop::BM(sgf_parse::go::Double::Double))
+        ));
+    }
+
+    #[test]
+    fn test_stones_to_annotated_sgf_includes_setup_stones_and_record_metadata() {
+        // A 9x9 board is the scenario this metadata matters most for: a
+        // missing SZ property would make a viewer silently default to
+        // 19x19 and misread every move's coordinates.
+        let board_size = (9, 9);
+        let moves = vec![katago::MoveRecord {
+            player: "B".into(),
+            mv: "E5".into(),
+            score_lead: 1.0,
+            candidates: vec![candidate("E5", 1.0), candidate("C3", 0.5)],
+        }];
+        let initial_stones = vec![("B".to_string(), "C3".to_string())];
+        let record = GameRecord {
+            board_size,
+            komi: 7.5,
+            rules: "tromp-taylor".into(),
+            players: (
+                Player {
+                    name: Some("alice".into()),
+                    rank: None,
+                },
+                Player {
+                    name: Some("bob".into()),
+                    rank: None,
+                },
+            ),
+            date: None,
+            result: Some(GameResult::BlackWins(3.5)),
+            id: Some("game-id".into()),
+        };
+
+        let tree = stones_to_annotated_sgf(&moves, &initial_stones, board_size, &record).unwrap();
+        let sgf_text = tree.as_go_node().unwrap().serialize();
+
+        assert!(sgf_text.contains("AB[cc]"));
+        assert!(sgf_text.contains("PB[alice]"));
+        assert!(sgf_text.contains("RE[B+3.5]"));
+
+        // Board size/komi/rules round-trip through the saved record so a
+        // 9x9 or 13x13 self-play game isn't misread as 19x19 by a viewer.
+        let reparsed = sgf_parse::parse(&sgf_text).unwrap().pop().unwrap();
+        let game = interpret_game(&reparsed).unwrap();
+        assert_eq!(game.board_size, board_size);
+        assert_eq!(game.komi, 7.5);
+        assert_eq!(game.rules, "tromp-taylor");
+    }
+
+    #[test]
+    fn test_save_game_collection_sgf_round_trips_as_two_games_with_distinct_ids() {
+        let board_size = DEFAULT_BOARD_SIZE;
+        let moves = vec![katago::MoveRecord {
+            player: "B".into(),
+            mv: "Q4".into(),
+            score_lead: 0.0,
+            candidates: vec![],
+        }];
+        let record_with_id = |id: &str| GameRecord {
+            board_size,
+            komi: 7.5,
+            rules: "tromp-taylor".into(),
+            id: Some(id.into()),
+            ..GameRecord::default()
+        };
+
+        let game_a =
+            stones_to_annotated_sgf(&moves, &[], board_size, &record_with_id("game-a")).unwrap();
+        let game_b =
+            stones_to_annotated_sgf(&moves, &[], board_size, &record_with_id("game-b")).unwrap();
+
+        let path = std::env::temp_dir().join(format!(
+            "go_tools_test_collection_{}.sgf",
+            std::process::id()
+        ));
+        let path_str = path.to_str().unwrap();
+
+        save_game_collection_sgf(&[game_a, game_b], path_str).unwrap();
+        let contents = std::fs::read_to_string(path_str).unwrap();
+        std::fs::remove_file(path_str).unwrap();
+
+        // The written file must parse back as two independent game trees,
+        // not a single tree with the second game nested as a bogus move
+        // node, and each must keep the GC id that makes it findable
+        // inside the batched collection.
+        let trees = sgf_parse::parse(&contents).unwrap();
+        assert_eq!(trees.len(), 2);
+        for (tree, id) in trees.iter().zip(["game-a", "game-b"]) {
+            let root = tree.as_go_node().unwrap();
+            assert!(root.serialize().contains(&format!("GC[{id}]")));
+        }
+    }
 }